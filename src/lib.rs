@@ -1,10 +1,17 @@
+use std::collections::HashMap;
+
 use cosmic_text::{
-    Align, Attrs, Buffer, FontSystem, Metrics, Shaping, SwashCache, SwashContent, Weight,
+    Align, Attrs, Buffer, CacheKey, FontSystem, Metrics, Shaping, SwashCache, SwashContent,
+    Weight, Wrap,
 };
 use fontdb::Family;
-use image::{GenericImage, ImageBuffer, Luma, Rgba};
+use image::{GenericImage, ImageBuffer, Pixel, Rgba};
 use thiserror::Error;
 
+/// Default capacity of the per-`TextPainter` colorized glyph tile cache. See
+/// [`TextPainter::set_glyph_tile_cache_capacity`].
+const DEFAULT_GLYPH_TILE_CACHE_CAPACITY: usize = 256;
+
 #[derive(Error, Debug)]
 pub enum Error {}
 
@@ -16,6 +23,7 @@ pub fn draw_text<I: GenericImage<Pixel = Rgba<u8>>>(image: &mut I, text_block: T
 pub struct TextPainter {
     font_system: FontSystem,
     swash_cache: SwashCache,
+    glyph_tile_cache: GlyphTileCache,
 }
 
 impl Default for TextPainter {
@@ -37,8 +45,17 @@ impl TextPainter {
         let font_system = FontSystem::new_with_locale_and_db(locale, font_database);
 
         let swash_cache = SwashCache::new();
+        let glyph_tile_cache = GlyphTileCache::new(DEFAULT_GLYPH_TILE_CACHE_CAPACITY);
 
-        Self { font_system, swash_cache }
+        Self { font_system, swash_cache, glyph_tile_cache }
+    }
+
+    /// Sets how many colorized glyph tiles (see [`GlyphTile`]) are kept around
+    /// before the least-recently-used ones are evicted. Defaults to
+    /// [`DEFAULT_GLYPH_TILE_CACHE_CAPACITY`]. Clamped to at least 1, since the
+    /// cache must always be able to hold the tile currently in use.
+    pub fn set_glyph_tile_cache_capacity(&mut self, capacity: usize) {
+        self.glyph_tile_cache.set_capacity(capacity);
     }
 
     pub fn paint_text_block<I: GenericImage<Pixel = Rgba<u8>>>(
@@ -46,21 +63,66 @@ impl TextPainter {
         image: &mut I,
         text_block: TextBlock,
     ) {
-        let (surface_width, surface_height) = image.dimensions();
+        let position = text_block.alignment;
+        let shaped = self.shape(&text_block);
 
-        let buffer = {
-            let mut buffer = self.shape_text_block(&text_block);
-            let measured_width = self.measure_text_block_width(&buffer);
+        self.draw_shaped(image, position, &shaped);
+    }
 
-            self.shape_again_if_needed(
-                &mut buffer,
-                text_block.text_align,
-                Some(measured_width),
-                text_block.max_height,
-            );
+    /// Shapes a [`TextBlock`] (a second time for center, right, end or justified
+    /// alignment; see `shape_again_if_needed`), caching its measured width/height
+    /// so that the result can be handed to [`TextPainter::draw_shaped`] without
+    /// shaping again.
+    pub fn shape(&mut self, text_block: &TextBlock) -> ShapedText {
+        let mut buffer = self.shape_text_block(text_block);
+        let width = self.measure_text_block_width(&buffer);
+        let height = self.measure_text_block_height(&buffer);
 
-            buffer
-        };
+        self.shape_again_if_needed(
+            &mut buffer,
+            text_block.text_align,
+            Some(width),
+            text_block.max_height,
+        );
+
+        let vertical_offset = text_block
+            .max_height
+            .map(|max_height| {
+                let leftover = (max_height - height).max(0.0);
+
+                match text_block.vertical_align {
+                    VerticalAlign::Top => 0.0,
+                    VerticalAlign::Middle => leftover / 2.0,
+                    VerticalAlign::Bottom => leftover,
+                }
+            })
+            .unwrap_or(0.0);
+
+        let span_effects = text_block
+            .text_spans
+            .iter()
+            .map(|span| SpanEffects { outline: span.outline, shadow: span.shadow })
+            .collect();
+
+        ShapedText {
+            buffer,
+            width,
+            height,
+            vertical_offset,
+            span_effects,
+            background: text_block.background,
+        }
+    }
+
+    /// Draws a [`ShapedText`] previously produced by [`TextPainter::shape`] (or
+    /// returned from [`TextPainter::measure`]), avoiding a second shaping pass.
+    pub fn draw_shaped<I: GenericImage<Pixel = Rgba<u8>>>(
+        &mut self,
+        image: &mut I,
+        position: TextBlockPosition,
+        shaped: &ShapedText,
+    ) {
+        let (surface_width, surface_height) = image.dimensions();
 
         enum TextDirection {
             Horizontal,
@@ -72,37 +134,48 @@ impl TextPainter {
                 AxisAlign::StartAt(value) => value,
                 AxisAlign::EndAt(value) => {
                     let measurement = match text_direction {
-                        TextDirection::Horizontal => self.measure_text_block_width(&buffer),
-                        TextDirection::Vertical => self.measure_text_block_height(&buffer),
+                        TextDirection::Horizontal => shaped.width,
+                        TextDirection::Vertical => shaped.height,
                     };
 
                     value - measurement
                 },
                 AxisAlign::CenterAt(value) => {
                     let measurement = match text_direction {
-                        TextDirection::Horizontal => self.measure_text_block_width(&buffer),
-                        TextDirection::Vertical => self.measure_text_block_height(&buffer),
+                        TextDirection::Horizontal => shaped.width,
+                        TextDirection::Vertical => shaped.height,
                     };
                     value - (measurement / 2.0)
                 },
                 AxisAlign::CenterAtCanvasCenter => {
                     let (surface_length, measurement) = match text_direction {
-                        TextDirection::Horizontal => {
-                            (surface_width, self.measure_text_block_width(&buffer))
-                        },
-                        TextDirection::Vertical => {
-                            (surface_height, self.measure_text_block_height(&buffer))
-                        },
+                        TextDirection::Horizontal => (surface_width, shaped.width),
+                        TextDirection::Vertical => (surface_height, shaped.height),
                     };
                     (surface_length as f32 / 2.0) - (measurement / 2.0)
                 },
             }
         };
 
-        let x = axis_position(text_block.alignment.x, TextDirection::Horizontal);
-        let y = axis_position(text_block.alignment.y, TextDirection::Vertical);
+        let x = axis_position(position.x, TextDirection::Horizontal);
+        let y = axis_position(position.y, TextDirection::Vertical) + shaped.vertical_offset;
+
+        if let Some((color, padding_px)) = shaped.background {
+            let (r, g, b, a) = color;
+            let background_width = (shaped.width + 2.0 * padding_px).max(0.0).round() as u32;
+            let background_height = (shaped.height + 2.0 * padding_px).max(0.0).round() as u32;
+            let background_image =
+                ImageBuffer::from_pixel(background_width, background_height, Rgba([r, g, b, a]));
+
+            image::imageops::overlay(
+                image,
+                &background_image,
+                (x - padding_px).round() as i64,
+                (y - padding_px).round() as i64,
+            );
+        }
 
-        self.add_text(image, x, y, &buffer);
+        self.add_text(image, x, y, &shaped.buffer, &shaped.span_effects);
     }
 
     fn add_text<I: GenericImage<Pixel = Rgba<u8>>>(
@@ -111,6 +184,7 @@ impl TextPainter {
         x: f32,
         y: f32,
         buffer: &Buffer,
+        span_effects: &[SpanEffects],
     ) {
         for run in buffer.layout_runs() {
             for glyph in run.glyphs.iter() {
@@ -132,28 +206,76 @@ impl TextPainter {
 
                 match glyph_image.content {
                     SwashContent::Mask | SwashContent::SubpixelMask => {
-                        // Grayscale
-                        let glyph_luma_image: ImageBuffer<Luma<u8>, &[u8]> =
-                            ImageBuffer::from_raw(glyph_width, glyph_height, &glyph_image.data[..])
-                                .unwrap();
-
-                        let (r, g, b, _a) = glyph
+                        let effects = span_effects.get(glyph.metadata).copied().unwrap_or_default();
+
+                        // Shadow and outline are drawn underneath the fill glyph,
+                        // shadow first so an outline can sit on top of it. Both
+                        // masks are padded so the effect can bleed outside the
+                        // glyph's own tight ink box instead of being clipped to it.
+                        if let Some((shadow_color, offset_x, offset_y, blur_px)) = effects.shadow {
+                            let (shadow_alpha, shadow_width, shadow_height) = box_blur_mask(
+                                &glyph_image.data,
+                                glyph_width,
+                                glyph_height,
+                                blur_px,
+                            );
+                            let (r, g, b, a) = shadow_color;
+
+                            let shadow_rgba_image: ImageBuffer<Rgba<u8>, Vec<u8>> =
+                                ImageBuffer::from_fn(shadow_width, shadow_height, |x, y| {
+                                    let coverage = shadow_alpha[(y * shadow_width + x) as usize];
+                                    Rgba([r, g, b, scale_alpha(coverage, a)])
+                                });
+
+                            image::imageops::overlay(
+                                image,
+                                &shadow_rgba_image,
+                                (glyph_x as f32 + offset_x - blur_px as f32).round() as i64,
+                                (glyph_y as f32 + offset_y - blur_px as f32).round() as i64,
+                            );
+                        }
+
+                        if let Some((outline_color, width_px)) = effects.outline {
+                            let radius = width_px.round().max(0.0) as u32;
+                            let (outline_alpha, outline_width, outline_height) =
+                                dilate_mask(&glyph_image.data, glyph_width, glyph_height, radius);
+                            let (r, g, b, a) = outline_color;
+
+                            let outline_rgba_image: ImageBuffer<Rgba<u8>, Vec<u8>> =
+                                ImageBuffer::from_fn(outline_width, outline_height, |x, y| {
+                                    let coverage = outline_alpha[(y * outline_width + x) as usize];
+                                    Rgba([r, g, b, scale_alpha(coverage, a)])
+                                });
+
+                            image::imageops::overlay(
+                                image,
+                                &outline_rgba_image,
+                                glyph_x as i64 - radius as i64,
+                                glyph_y as i64 - radius as i64,
+                            );
+                        }
+
+                        let color = glyph
                             .color_opt
                             .map(|c| c.as_rgba_tuple())
                             .unwrap_or((255, 255, 255, 255));
 
-                        let glyph_rgba_image: ImageBuffer<Rgba<u8>, Vec<u8>> =
-                            ImageBuffer::from_fn(glyph_width, glyph_height, |x, y| {
-                                let glyph_alpha = glyph_luma_image.get_pixel(x, y)[0];
-                                Rgba([r, g, b, glyph_alpha])
-                            });
-
-                        image::imageops::overlay(
-                            image,
-                            &glyph_rgba_image,
-                            glyph_x as i64,
-                            glyph_y as i64,
-                        );
+                        let tile_key = GlyphTileKey {
+                            cache_key: physical_glyph.cache_key,
+                            placement: (
+                                glyph_image.placement.left,
+                                glyph_image.placement.top,
+                                glyph_width,
+                                glyph_height,
+                            ),
+                            color,
+                        };
+
+                        let tile = self.glyph_tile_cache.get_or_insert_with(tile_key, || {
+                            GlyphTile::colorize(&glyph_image.data, glyph_width, glyph_height, color)
+                        });
+
+                        blend_tile(image, tile, glyph_x, glyph_y);
                     },
                     SwashContent::Color => {
                         // Color
@@ -187,7 +309,7 @@ impl TextPainter {
             default_attrs = default_attrs.family(Family::Name(font));
         }
 
-        let spans = text_block.text_spans.iter().map(|span| {
+        let spans = text_block.text_spans.iter().enumerate().map(|(index, span)| {
             let text: &str = &span.text;
             let mut metrics = default_attrs
                 .metrics(Metrics::relative(span.font_size, span.line_height.unwrap_or(1.0)));
@@ -200,6 +322,7 @@ impl TextPainter {
 
             let (r, g, b, a) = span.color;
             metrics = metrics.color(cosmic_text::Color::rgba(r, g, b, a));
+            metrics = metrics.metadata(index);
 
             (text, metrics)
         });
@@ -207,6 +330,7 @@ impl TextPainter {
         buffer.set_rich_text(&mut self.font_system, spans, default_attrs, Shaping::Advanced);
 
         buffer.set_size(&mut self.font_system, text_block.max_width, text_block.max_height);
+        buffer.set_wrap(&mut self.font_system, text_block.wrap.into());
 
         for line in &mut buffer.lines {
             let align = match text_block.text_align {
@@ -226,14 +350,11 @@ impl TextPainter {
         buffer
     }
 
-    pub fn measure(&mut self, text_block: &TextBlock) -> (f32, f32) {
-        let mut buffer = self.shape_text_block(text_block);
-        let width = self.measure_text_block_width(&buffer);
-        let height = self.measure_text_block_height(&buffer);
-
-        self.shape_again_if_needed(&mut buffer, text_block.text_align, Some(width), Some(height));
-
-        (width, height)
+    /// Shapes `text_block` and returns its measured size along with the shaped
+    /// layout itself, so the result can be passed straight into
+    /// [`TextPainter::draw_shaped`] without shaping the block a second time.
+    pub fn measure(&mut self, text_block: &TextBlock) -> ShapedText {
+        self.shape(text_block)
     }
 
     fn measure_text_block_width(&self, buffer: &Buffer) -> f32 {
@@ -272,16 +393,385 @@ impl TextPainter {
     }
 }
 
+/// Identifies a colorized glyph tile in the [`GlyphTileCache`]: the rasterized
+/// glyph (`cache_key`, as produced by `swash_cache`), where that raster sits
+/// relative to the glyph origin, and the color it was tinted with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphTileKey {
+    cache_key: CacheKey,
+    placement: (i32, i32, u32, u32),
+    color: (u8, u8, u8, u8),
+}
+
+/// A small RGBA tile holding a glyph's mask alpha already tinted with its
+/// span color, ready to be blended directly into a destination image.
+struct GlyphTile {
+    width: u32,
+    height: u32,
+    /// Straight-alpha RGBA bytes, `width * height * 4` long.
+    rgba: Vec<u8>,
+}
+
+impl GlyphTile {
+    fn colorize(mask: &[u8], width: u32, height: u32, color: (u8, u8, u8, u8)) -> Self {
+        // Matches the pre-existing fill behavior: only mask coverage drives alpha,
+        // the span color's own alpha channel is ignored. Outline/shadow colors
+        // are the documented exception; see `scale_alpha`.
+        let (r, g, b, _a) = color;
+        let mut rgba = vec![0u8; mask.len() * 4];
+
+        for (alpha, pixel) in mask.iter().zip(rgba.chunks_exact_mut(4)) {
+            pixel.copy_from_slice(&[r, g, b, *alpha]);
+        }
+
+        Self { width, height, rgba }
+    }
+}
+
+/// A cached tile plus its position in the [`GlyphTileCache`]'s recency list:
+/// `prev` points toward the more-recently-used side (the head), `next`
+/// toward the less-recently-used side (the tail).
+struct GlyphTileEntry {
+    tile: GlyphTile,
+    prev: Option<GlyphTileKey>,
+    next: Option<GlyphTileKey>,
+}
+
+/// A bounded LRU cache of colorized glyph tiles, so that drawing the same
+/// glyph in the same color repeatedly (the common case for runs of text)
+/// reuses the already-tinted tile instead of re-tinting it from the mask.
+///
+/// Recency is tracked with an intrusive doubly linked list threaded through
+/// `entries` by key, so both a cache hit (move-to-front) and an eviction
+/// (drop the tail) are O(1) instead of scanning the whole cache.
+struct GlyphTileCache {
+    capacity: usize,
+    entries: HashMap<GlyphTileKey, GlyphTileEntry>,
+    most_recent: Option<GlyphTileKey>,
+    least_recent: Option<GlyphTileKey>,
+}
+
+impl GlyphTileCache {
+    /// Capacity is clamped to at least 1: `get_or_insert_with` always returns
+    /// a reference into the cache, so it must hold at least the tile it just
+    /// built or touched.
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            most_recent: None,
+            least_recent: None,
+        }
+    }
+
+    fn get_or_insert_with(
+        &mut self,
+        key: GlyphTileKey,
+        build: impl FnOnce() -> GlyphTile,
+    ) -> &GlyphTile {
+        if self.entries.contains_key(&key) {
+            self.move_to_front(key);
+        } else {
+            if self.entries.len() >= self.capacity {
+                self.evict_least_recent();
+            }
+
+            self.entries.insert(key, GlyphTileEntry { tile: build(), prev: None, next: None });
+            self.link_front(key);
+        }
+
+        &self.entries.get(&key).expect("key was just inserted or already present").tile
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+
+        while self.entries.len() > self.capacity {
+            self.evict_least_recent();
+        }
+    }
+
+    fn move_to_front(&mut self, key: GlyphTileKey) {
+        if self.most_recent == Some(key) {
+            return;
+        }
+
+        self.unlink(key);
+        self.link_front(key);
+    }
+
+    fn evict_least_recent(&mut self) {
+        let Some(lru_key) = self.least_recent else { return };
+
+        self.unlink(lru_key);
+        self.entries.remove(&lru_key);
+    }
+
+    /// Detaches `key` from the recency list, patching up its neighbors (and
+    /// `most_recent`/`least_recent`) without removing it from `entries`.
+    fn unlink(&mut self, key: GlyphTileKey) {
+        let (prev, next) = {
+            let entry = self.entries.get(&key).expect("key is linked");
+            (entry.prev, entry.next)
+        };
+
+        match prev {
+            Some(p) => self.entries.get_mut(&p).expect("prev is linked").next = next,
+            None => self.most_recent = next,
+        }
+
+        match next {
+            Some(n) => self.entries.get_mut(&n).expect("next is linked").prev = prev,
+            None => self.least_recent = prev,
+        }
+    }
+
+    /// Makes `key` the most-recently-used entry. `key` must already be in
+    /// `entries`, with stale/absent `prev`/`next` links.
+    fn link_front(&mut self, key: GlyphTileKey) {
+        let old_head = self.most_recent;
+
+        {
+            let entry = self.entries.get_mut(&key).expect("key is present");
+            entry.prev = None;
+            entry.next = old_head;
+        }
+
+        if let Some(head) = old_head {
+            self.entries.get_mut(&head).expect("old head is linked").prev = Some(key);
+        }
+
+        self.most_recent = Some(key);
+        self.least_recent.get_or_insert(key);
+    }
+}
+
+/// Blends a colorized glyph tile directly into `image` at `(origin_x, origin_y)`,
+/// reading/writing destination pixels in place rather than building an
+/// intermediate `ImageBuffer` and calling `image::imageops::overlay`.
+fn blend_tile<I: GenericImage<Pixel = Rgba<u8>>>(
+    image: &mut I,
+    tile: &GlyphTile,
+    origin_x: i32,
+    origin_y: i32,
+) {
+    let (surface_width, surface_height) = image.dimensions();
+
+    for ty in 0..tile.height {
+        for tx in 0..tile.width {
+            let offset = ((ty * tile.width + tx) * 4) as usize;
+            let src_alpha = tile.rgba[offset + 3];
+            if src_alpha == 0 {
+                continue;
+            }
+
+            let dst_x = origin_x + tx as i32;
+            let dst_y = origin_y + ty as i32;
+            let out_of_bounds = dst_x < 0
+                || dst_y < 0
+                || dst_x as u32 >= surface_width
+                || dst_y as u32 >= surface_height;
+            if out_of_bounds {
+                continue;
+            }
+
+            let mut dst_pixel = image.get_pixel(dst_x as u32, dst_y as u32);
+            let src_pixel =
+                Rgba([tile.rgba[offset], tile.rgba[offset + 1], tile.rgba[offset + 2], src_alpha]);
+            dst_pixel.blend(&src_pixel);
+            image.put_pixel(dst_x as u32, dst_y as u32, dst_pixel);
+        }
+    }
+}
+
+/// Scales an 8-bit mask coverage value by a color's alpha channel, so an
+/// outline/shadow color's own transparency is honored rather than ignored.
+fn scale_alpha(coverage: u8, color_alpha: u8) -> u8 {
+    ((coverage as u32 * color_alpha as u32) / 255) as u8
+}
+
+/// Dilates an 8-bit coverage mask by taking, for every pixel, the maximum
+/// alpha found within `radius` pixels of it (a disc-shaped structuring
+/// element). Used to grow a glyph's fill coverage into an outline.
+///
+/// The glyph's mask is tight to its ink, so the output is padded by `radius`
+/// on every side (returned alongside its own width/height) rather than
+/// clipped to the source size — otherwise the dilation could only ever fill
+/// in transparent interior pixels that the crisp fill paints over anyway,
+/// and the outline would never actually extend past the glyph.
+fn dilate_mask(data: &[u8], width: u32, height: u32, radius: u32) -> (Vec<u8>, u32, u32) {
+    let (src_width, src_height, radius) = (width as i64, height as i64, radius as i64);
+    let padded_width = src_width + 2 * radius;
+    let padded_height = src_height + 2 * radius;
+    let mut out = vec![0u8; (padded_width * padded_height) as usize];
+
+    for oy in 0..padded_height {
+        for ox in 0..padded_width {
+            let (center_x, center_y) = (ox - radius, oy - radius);
+            let mut max_alpha = 0u8;
+
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if dx * dx + dy * dy > radius * radius {
+                        continue;
+                    }
+
+                    let (sx, sy) = (center_x + dx, center_y + dy);
+                    if sx < 0 || sy < 0 || sx >= src_width || sy >= src_height {
+                        continue;
+                    }
+
+                    max_alpha = max_alpha.max(data[(sy * src_width + sx) as usize]);
+                }
+            }
+
+            out[(oy * padded_width + ox) as usize] = max_alpha;
+        }
+    }
+
+    (out, padded_width as u32, padded_height as u32)
+}
+
+/// Softens an 8-bit coverage mask with `passes` rounds of a 3x3 box blur.
+/// Used to soften a glyph's fill coverage into a drop shadow.
+///
+/// Each pass can spread coverage one pixel further out, so (like
+/// [`dilate_mask`]) the output is padded by `passes` on every side rather
+/// than clipped to the source size, or the shadow could never blur past the
+/// glyph's own tight ink box.
+fn box_blur_mask(data: &[u8], width: u32, height: u32, passes: u32) -> (Vec<u8>, u32, u32) {
+    let (src_width, src_height, passes_i64) = (width as i64, height as i64, passes as i64);
+    let padded_width = src_width + 2 * passes_i64;
+    let padded_height = src_height + 2 * passes_i64;
+
+    let mut current = vec![0u8; (padded_width * padded_height) as usize];
+    for y in 0..src_height {
+        for x in 0..src_width {
+            let (px, py) = (x + passes_i64, y + passes_i64);
+            current[(py * padded_width + px) as usize] = data[(y * src_width + x) as usize];
+        }
+    }
+
+    for _ in 0..passes {
+        let mut next = vec![0u8; current.len()];
+
+        for y in 0..padded_height {
+            for x in 0..padded_width {
+                let mut sum = 0u32;
+                let mut count = 0u32;
+
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        let (sx, sy) = (x + dx, y + dy);
+                        if sx < 0 || sy < 0 || sx >= padded_width || sy >= padded_height {
+                            continue;
+                        }
+
+                        sum += current[(sy * padded_width + sx) as usize] as u32;
+                        count += 1;
+                    }
+                }
+
+                next[(y * padded_width + x) as usize] = (sum / count.max(1)) as u8;
+            }
+        }
+
+        current = next;
+    }
+
+    (current, padded_width as u32, padded_height as u32)
+}
+
+/// A [`TextBlock`] that has already been shaped (laid out) by a [`TextPainter`],
+/// along with its measured width and height. Shaping is the expensive part of
+/// drawing text, so measuring a block and then drawing it should shape once and
+/// reuse the result via [`TextPainter::draw_shaped`] rather than shaping twice.
+pub struct ShapedText {
+    buffer: Buffer,
+    width: f32,
+    height: f32,
+    vertical_offset: f32,
+    span_effects: Vec<SpanEffects>,
+    background: Option<((u8, u8, u8, u8), f32)>,
+}
+
+/// The per-`Text`-span outline/shadow settings, carried alongside a shaped
+/// buffer and looked up per-glyph via `cosmic_text`'s glyph metadata.
+#[derive(Debug, Default, Clone, Copy)]
+struct SpanEffects {
+    outline: Option<((u8, u8, u8, u8), f32)>,
+    shadow: Option<((u8, u8, u8, u8), f32, f32, u32)>,
+}
+
+impl ShapedText {
+    /// The measured width of the shaped text block, in pixels.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// The measured height of the shaped text block, in pixels.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TextBlock {
     pub alignment: TextBlockPosition,
     pub max_width: Option<f32>,
     pub max_height: Option<f32>,
     pub text_align: TextAlign,
+    /// How lines should be wrapped when they exceed `max_width`.
+    pub wrap: WrapMode,
+    /// Where the text block should sit within `max_height`, once its top edge
+    /// has been positioned by `alignment`. Has no effect if `max_height` is `None`.
+    pub vertical_align: VerticalAlign,
     pub text_spans: Vec<Text>,
     /// The default font to use for all text spans.
     /// Can be overrided with `Text.font`.
     pub font: Option<&'static str>,
+    /// If present, fills a rectangle behind the text block with the given
+    /// color, extending `padding_px` beyond the block's measured bounds on
+    /// every side. Useful for a subtitle-style panel behind the text.
+    pub background: Option<((u8, u8, u8, u8), f32)>,
+}
+
+/// Controls how a line of text is broken when it exceeds `TextBlock::max_width`.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum WrapMode {
+    /// Wrap at word boundaries, falling back to breaking mid-word if a single
+    /// word doesn't fit on a line.
+    #[default]
+    WordOrGlyph,
+    /// Only wrap at word boundaries.
+    Word,
+    /// Wrap at any glyph, breaking mid-word if needed.
+    Glyph,
+    /// Never wrap; lines overflow `max_width`.
+    None,
+}
+
+impl From<WrapMode> for Wrap {
+    fn from(wrap_mode: WrapMode) -> Self {
+        match wrap_mode {
+            WrapMode::WordOrGlyph => Wrap::WordOrGlyph,
+            WrapMode::Word => Wrap::Word,
+            WrapMode::Glyph => Wrap::Glyph,
+            WrapMode::None => Wrap::None,
+        }
+    }
+}
+
+/// Controls where a `TextBlock` sits vertically within its `max_height`.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum VerticalAlign {
+    /// Align to the top of the box.
+    #[default]
+    Top,
+    /// Center within the box.
+    Middle,
+    /// Align to the bottom of the box.
+    Bottom,
 }
 
 /// Determines positioning for a TextBlock.
@@ -352,6 +842,16 @@ pub struct Text {
     /// line_height is a relative value. Multiply it by the font-size
     /// to arrive at the absolute line height value.
     pub line_height: Option<f32>,
+    /// If present, draws a solid outline around the glyph fill using the
+    /// given color and stroke width in pixels, underneath the fill. Unlike
+    /// `color`, this color's alpha channel is honored and scales the mask
+    /// coverage, so a semi-transparent outline is possible.
+    pub outline: Option<((u8, u8, u8, u8), f32)>,
+    /// If present, draws a drop shadow behind the glyph fill: color, x/y
+    /// pixel offset, and how many box-blur passes to soften it by. Unlike
+    /// `color`, this color's alpha channel is honored and scales the mask
+    /// coverage, so a semi-transparent shadow is possible.
+    pub shadow: Option<((u8, u8, u8, u8), f32, f32, u32)>,
 }
 
 impl Text {
@@ -363,6 +863,8 @@ impl Text {
             color: (255, 255, 255, 255),
             font: None,
             line_height: None,
+            outline: None,
+            shadow: None,
         }
     }
 
@@ -385,8 +887,11 @@ impl TextBlock {
             max_width: None,
             max_height: None,
             text_align: Default::default(),
+            wrap: Default::default(),
+            vertical_align: Default::default(),
             text_spans: vec![],
             font: None,
+            background: None,
         }
     }
 
@@ -396,8 +901,11 @@ impl TextBlock {
             max_width: None,
             max_height: None,
             text_align: Default::default(),
+            wrap: Default::default(),
+            vertical_align: Default::default(),
             text_spans: vec![Text::new(text)],
             font: None,
+            background: None,
         }
     }
 
@@ -416,6 +924,21 @@ impl TextBlock {
         self
     }
 
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+        self.vertical_align = vertical_align;
+        self
+    }
+
+    pub fn with_background(mut self, color: (u8, u8, u8, u8), padding_px: f32) -> Self {
+        self.background = Some((color, padding_px));
+        self
+    }
+
     pub fn with_text_blocks(mut self, text_spans: impl Iterator<Item = Text>) -> Self {
         self.text_spans = text_spans.collect();
         self